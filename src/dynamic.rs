@@ -0,0 +1,289 @@
+//! Dynamic insert/remove through Bentley–Saxe dynamization.
+//!
+//! A [`KdTree`] is immutable once built. [`DynamicKdTree`] layers mutability on
+//! top of it with the logarithmic (Bentley–Saxe) method used by dynamic
+//! kd-forest designs: it holds a small stack of static [`KdTree`]s whose point
+//! counts are distinct powers of two. Inserting a point creates a singleton and
+//! then, while a tree already occupies the current level, merges the two and
+//! rebuilds one level up — so inserts are O(log n) amortized and the O(n)
+//! rebuild work is spread out. Deletions are recorded in a tombstone set that
+//! queries skip, and a full compaction runs once tombstoned points make up half
+//! of all stored points.
+//!
+//! Each component is an ordinary [`KdTree`], so it keeps the full static query
+//! surface; [`DynamicKdTree`] simply fans a query out across the live trees and
+//! merges the candidate sets.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use num_traits::Float;
+
+use crate::{ItemAndDistance, KdPoint, KdTree};
+
+/// A mutable kd-tree supporting `insert` and `remove`, built from a forest of
+/// static [`KdTree`]s of power-of-two sizes (Bentley–Saxe dynamization).
+///
+/// Build one with [`build`](DynamicKdTree::build) (integer coordinates) or
+/// [`build_by_ordered_float`](DynamicKdTree::build_by_ordered_float)
+/// (floating-point coordinates), or start empty with
+/// [`new`](DynamicKdTree::new) / [`new_by_ordered_float`](DynamicKdTree::new_by_ordered_float).
+///
+/// Deletions are tracked by value, so the stored points should be distinct: if
+/// the same point is inserted twice and then [`remove`](DynamicKdTree::remove)d
+/// once, both copies are skipped by subsequent queries even though only one was
+/// meant to go. Use distinct points (e.g. wrap coordinates with a unique id) if
+/// you need per-copy deletion.
+#[derive(Clone)]
+pub struct DynamicKdTree<T: KdPoint> {
+    /// Live component trees: `levels[i]` is either empty or holds exactly
+    /// `2^i` points.
+    levels: Vec<Option<KdTree<T>>>,
+    /// Points marked deleted but still physically present in a component tree.
+    tombstones: Vec<T>,
+    /// Per-axis comparator shared by every component rebuild.
+    compare: fn(&T, &T, usize) -> Ordering,
+}
+
+// Hand-written so that `Debug` only requires `T: Debug`; deriving it would
+// leak the `KdTree<T>: Debug` bound (`T::Scalar: Debug`, via the periodic box
+// lengths) onto every use of a `DynamicKdTree`.
+impl<T: KdPoint + fmt::Debug> fmt::Debug for DynamicKdTree<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let levels: Vec<&[T]> = self.levels.iter().flatten().map(KdTree::as_slice).collect();
+        f.debug_struct("DynamicKdTree")
+            .field("levels", &levels)
+            .field("tombstones", &self.tombstones)
+            .finish()
+    }
+}
+
+impl<T: KdPoint> DynamicKdTree<T>
+where
+    T::Scalar: Ord,
+{
+    /// Creates an empty tree for points with totally-ordered (e.g. integer)
+    /// coordinates.
+    pub fn new() -> Self {
+        Self::with_comparator(|a, b, k| a.at(k).cmp(&b.at(k)))
+    }
+
+    /// Builds a tree from points with totally-ordered coordinates.
+    pub fn build(points: Vec<T>) -> Self {
+        let mut tree = Self::new();
+        tree.rebuild_from(points);
+        tree
+    }
+}
+
+impl<T: KdPoint> Default for DynamicKdTree<T>
+where
+    T::Scalar: Ord,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: KdPoint> DynamicKdTree<T>
+where
+    T::Scalar: Float,
+{
+    /// Creates an empty tree for points with floating-point coordinates.
+    pub fn new_by_ordered_float() -> Self {
+        Self::with_comparator(|a, b, k| {
+            ordered_float::OrderedFloat(a.at(k)).cmp(&ordered_float::OrderedFloat(b.at(k)))
+        })
+    }
+
+    /// Builds a tree from points with floating-point coordinates.
+    pub fn build_by_ordered_float(points: Vec<T>) -> Self {
+        let mut tree = Self::new_by_ordered_float();
+        tree.rebuild_from(points);
+        tree
+    }
+}
+
+impl<T: KdPoint> DynamicKdTree<T> {
+    fn with_comparator(compare: fn(&T, &T, usize) -> Ordering) -> Self {
+        Self {
+            levels: Vec::new(),
+            tombstones: Vec::new(),
+            compare,
+        }
+    }
+
+    /// Returns the number of live (non-tombstoned) points.
+    pub fn len(&self) -> usize {
+        self.stored_len() - self.tombstones.len()
+    }
+
+    /// Returns `true` if the tree holds no live points.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Total points physically stored across the component trees, including
+    /// those shadowed by a tombstone.
+    fn stored_len(&self) -> usize {
+        self.levels.iter().flatten().map(KdTree::len).sum()
+    }
+
+    /// Inserts a point, merging equal-sized component trees upward so that each
+    /// level keeps at most one tree of `2^level` points.
+    pub fn insert(&mut self, item: T) {
+        let mut batch = vec![item];
+        let mut level = 0;
+        loop {
+            if level == self.levels.len() {
+                self.levels.push(None);
+            }
+            match self.levels[level].take() {
+                Some(tree) => {
+                    batch.extend(tree.into_vec());
+                    level += 1;
+                }
+                None => {
+                    self.levels[level] = Some(KdTree::build_by(batch, self.compare));
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Distributes `points` across the component levels so that level `i` holds
+    /// a tree exactly when bit `i` of `points.len()` is set. Resets tombstones.
+    fn rebuild_from(&mut self, mut points: Vec<T>) {
+        self.levels.clear();
+        self.tombstones.clear();
+        let n = points.len();
+        let mut level = 0;
+        while (n >> level) != 0 {
+            if (n >> level) & 1 == 1 {
+                let size = 1usize << level;
+                let rest = points.split_off(points.len() - size);
+                self.levels.push(Some(KdTree::build_by(rest, self.compare)));
+            } else {
+                self.levels.push(None);
+            }
+            level += 1;
+        }
+    }
+}
+
+impl<T: KdPoint + PartialEq> DynamicKdTree<T> {
+    /// Marks `item` as deleted, returning `true` if a matching live point was
+    /// found. The point stays in its component tree until the next compaction,
+    /// which is triggered once tombstoned points make up half of all stored
+    /// points.
+    ///
+    /// Matching is by value: if several stored points are equal to `item`, this
+    /// tombstones the value and every equal copy is dropped from later queries,
+    /// not just one. See the type-level note on distinct points.
+    pub fn remove(&mut self, item: &T) -> bool
+    where
+        T: Clone,
+    {
+        let copies: usize = self
+            .levels
+            .iter()
+            .flatten()
+            .map(|tree| tree.iter().filter(|p| *p == item).count())
+            .sum();
+        let present = copies > 0 && !self.is_tombstoned(item);
+        if present {
+            // One tombstone per stored copy, so `len()` and the padding in
+            // `nearests` account for every copy this hides.
+            for _ in 0..copies {
+                self.tombstones.push(item.clone());
+            }
+            if self.tombstones.len() * 2 >= self.stored_len() {
+                self.compact();
+            }
+        }
+        present
+    }
+
+    /// Rebuilds the forest from the live points, physically dropping every
+    /// point whose value is tombstoned.
+    ///
+    /// Matching is by value, same as [`remove`](Self::remove): if a
+    /// tombstoned value has several physical copies (from inserting the same
+    /// point more than once), every copy is dropped here, not just one. Doing
+    /// otherwise would silently resurrect a value that queries had already
+    /// been hiding in full.
+    fn compact(&mut self)
+    where
+        T: Clone,
+    {
+        let tombstones = std::mem::take(&mut self.tombstones);
+        let mut remaining = Vec::new();
+        for tree in self.levels.drain(..).flatten() {
+            for p in tree.into_vec() {
+                if !tombstones.contains(&p) {
+                    remaining.push(p);
+                }
+            }
+        }
+        self.rebuild_from(remaining);
+    }
+
+    fn is_tombstoned(&self, item: &T) -> bool {
+        self.tombstones.iter().any(|t| t == item)
+    }
+
+    /// Finds the single nearest neighbor of `query`, or `None` if no live point
+    /// exists. Uses squared [`Euclidean`](crate::Euclidean) distance.
+    pub fn nearest<P: KdPoint<Scalar = T::Scalar>>(
+        &self,
+        query: &P,
+    ) -> Option<ItemAndDistance<'_, T, T::Scalar>> {
+        self.nearests(query, 1).into_iter().next()
+    }
+
+    /// Finds the `k` nearest live neighbors of `query`, sorted by increasing
+    /// distance.
+    ///
+    /// Each component tree is asked for `k + tombstones` candidates so that,
+    /// after discarding tombstoned points, at least `k` genuine neighbors
+    /// survive per tree.
+    pub fn nearests<P: KdPoint<Scalar = T::Scalar>>(
+        &self,
+        query: &P,
+        k: usize,
+    ) -> Vec<ItemAndDistance<'_, T, T::Scalar>> {
+        let pad = self.tombstones.len();
+        let mut candidates = Vec::new();
+        for tree in self.levels.iter().flatten() {
+            for entry in tree.nearests(query, k + pad) {
+                if !self.is_tombstoned(entry.item) {
+                    candidates.push(entry);
+                }
+            }
+        }
+        candidates.sort_by(|a, b| {
+            a.squared_distance
+                .partial_cmp(&b.squared_distance)
+                .unwrap_or(Ordering::Equal)
+        });
+        candidates.truncate(k);
+        candidates
+    }
+
+    /// Returns every live point strictly within `radius` of `query`.
+    pub fn within_radius<P: KdPoint<Scalar = T::Scalar>>(
+        &self,
+        query: &P,
+        radius: T::Scalar,
+    ) -> Vec<&T> {
+        let mut out = Vec::new();
+        for tree in self.levels.iter().flatten() {
+            for p in tree.within_radius(query, radius) {
+                if !self.is_tombstoned(p) {
+                    out.push(p);
+                }
+            }
+        }
+        out
+    }
+}
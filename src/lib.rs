@@ -0,0 +1,831 @@
+//! A fast, generic k-dimensional tree for nearest-neighbor and range queries.
+//!
+//! The tree is built once from a collection of points and is immutable
+//! afterwards. Points are any type implementing [`KdPoint`]; fixed-size arrays
+//! such as `[f64; 3]` are supported out of the box, and `nalgebra` points and
+//! vectors behind the `nalgebra` feature.
+//!
+//! ```no_run
+//! use kd_tree::KdTree;
+//! let tree = KdTree::build_by_ordered_float(vec![[0.0, 0.0], [1.0, 1.0]]);
+//! let nearest = tree.nearest(&[0.1, 0.1]).unwrap();
+//! assert_eq!(nearest.item, &[0.0, 0.0]);
+//! ```
+
+use std::cmp::Ordering;
+
+use num_traits::{Float, Num, One, Zero};
+
+mod dynamic;
+mod metric;
+mod periodic;
+mod vp_tree;
+
+pub use dynamic::DynamicKdTree;
+pub use metric::{Chebyshev, Euclidean, Manhattan, Metric, Minkowski};
+pub use periodic::Periodic;
+pub use vp_tree::VpTree;
+
+#[cfg(test)]
+mod tests;
+
+/// A point living in a fixed-dimensional Cartesian space.
+///
+/// Implement this for any type you want to store in a [`KdTree`]. The tree only
+/// ever reads coordinates through [`KdPoint::at`]; it never mutates points.
+pub trait KdPoint {
+    /// The scalar type of a single coordinate.
+    type Scalar: Num + Copy + PartialOrd;
+
+    /// The number of dimensions of the space.
+    const DIM: usize;
+
+    /// Returns the `k`-th coordinate of this point.
+    ///
+    /// `k` is always in `0..Self::DIM`.
+    fn at(&self, k: usize) -> Self::Scalar;
+}
+
+impl<T: Num + Copy + PartialOrd, const N: usize> KdPoint for [T; N] {
+    type Scalar = T;
+    const DIM: usize = N;
+    fn at(&self, k: usize) -> T {
+        self[k]
+    }
+}
+
+/// A point paired with its distance to a query.
+///
+/// Returned by [`KdTree::nearest`] and [`KdTree::nearests`]. The distance is
+/// in the metric's comparison domain: for the default [`Euclidean`] (and
+/// [`Periodic`]) metric it is kept squared to avoid a square root on the hot
+/// path, so take `sqrt()` yourself if you need the true Euclidean distance.
+/// Other metrics report their own domain value (see [`Metric`]), and
+/// [`VpTree`] reports the raw distance from its distance function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ItemAndDistance<'a, T, Scalar> {
+    /// The stored point.
+    pub item: &'a T,
+    /// The distance from `item` to the query point, in the metric's comparison
+    /// domain (squared for the default Euclidean metric).
+    pub squared_distance: Scalar,
+}
+
+/// Tunables for the advanced query entry point
+/// [`nearests_advanced`](KdTree::nearests_advanced).
+#[derive(Clone, Copy, Debug)]
+pub struct SearchParams<S> {
+    /// If set, neighbors farther than this radius are never returned, so a
+    /// sparse neighborhood yields fewer than `k` results.
+    ///
+    /// Unlike [`within_radius`](KdTree::within_radius), which is strict (a
+    /// point exactly at the radius is excluded), this bound is inclusive: a
+    /// neighbor at exactly `max_radius` is kept. Mind the difference if you
+    /// switch between `nearests_advanced` and the `within_radius` family with
+    /// the same radius value.
+    pub max_radius: Option<S>,
+    /// When `false`, a stored point coinciding exactly with the query (distance
+    /// zero) is excluded — the usual choice when building a k-NN graph over the
+    /// stored points.
+    pub allow_self_match: bool,
+    /// When `true` (the default), results come back sorted by increasing
+    /// distance; otherwise they are returned in heap order.
+    pub sort_results: bool,
+}
+
+impl<S> Default for SearchParams<S> {
+    fn default() -> Self {
+        Self {
+            max_radius: None,
+            allow_self_match: true,
+            sort_results: true,
+        }
+    }
+}
+
+/// Outcome of an advanced query: the neighbors plus the number of tree nodes
+/// visited ("touch count"), useful for tuning and for comparing exact against
+/// approximate search.
+#[derive(Clone, Debug)]
+pub struct SearchResult<'a, T, Scalar> {
+    /// The matching neighbors, sorted by distance unless
+    /// [`SearchParams::sort_results`] was `false`.
+    pub neighbors: Vec<ItemAndDistance<'a, T, Scalar>>,
+    /// The number of tree nodes the traversal touched.
+    pub touched: usize,
+}
+
+/// An immutable k-dimensional tree.
+///
+/// Build one with [`KdTree::build`] (for integer coordinates) or
+/// [`KdTree::build_by_ordered_float`] (for floating-point coordinates), then
+/// query it with [`nearest`](KdTree::nearest), [`nearests`](KdTree::nearests),
+/// [`within`](KdTree::within) and [`within_radius`](KdTree::within_radius).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KdTree<T: KdPoint> {
+    items: Vec<T>,
+    /// Per-dimension box lengths for periodic queries, or `None` for an
+    /// ordinary open-space tree. Set only by [`KdTree::build_periodic`].
+    periodic: Option<Periodic<T::Scalar>>,
+}
+
+#[cfg(feature = "serde")]
+impl<T: KdPoint + serde::Serialize> serde::Serialize for KdTree<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.items.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: KdPoint + serde::Deserialize<'de>> serde::Deserialize<'de> for KdTree<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // The stored points are already in tree order, so no rebuild is needed.
+        Ok(Self {
+            items: Vec::<T>::deserialize(deserializer)?,
+            periodic: None,
+        })
+    }
+}
+
+/// Convenience alias for a three-dimensional [`KdTree`].
+pub type KdTree3<T> = KdTree<T>;
+/// The general n-dimensional tree; identical to [`KdTree`], kept for symmetry
+/// with the public type names.
+pub type KdTreeN<T> = KdTree<T>;
+
+impl<T: KdPoint> KdTree<T>
+where
+    T::Scalar: Ord,
+{
+    /// Builds a tree from points with totally-ordered (e.g. integer) coordinates.
+    pub fn build(points: Vec<T>) -> Self {
+        Self::build_by(points, |a, b, k| a.at(k).cmp(&b.at(k)))
+    }
+}
+
+impl<T: KdPoint> KdTree<T>
+where
+    T::Scalar: Float,
+{
+    /// Builds a tree from points with floating-point coordinates.
+    ///
+    /// Coordinates are compared through [`ordered_float`], so `NaN` coordinates
+    /// sort consistently rather than panicking.
+    pub fn build_by_ordered_float(points: Vec<T>) -> Self {
+        Self::build_by(points, |a, b, k| {
+            ordered_float::OrderedFloat(a.at(k)).cmp(&ordered_float::OrderedFloat(b.at(k)))
+        })
+    }
+
+    /// Builds a tree over a periodic box of per-dimension lengths `box_lengths`.
+    ///
+    /// The default queries ([`nearest`](KdTree::nearest),
+    /// [`nearests`](KdTree::nearests), [`within_radius`](KdTree::within_radius))
+    /// then use minimum-image distance, wrapping across the box edges. Pruning
+    /// uses the wrapped per-axis gap, so a neighbor reachable only across a box
+    /// boundary is never missed.
+    ///
+    /// `box_lengths` must have one entry per dimension (`T::DIM`).
+    pub fn build_periodic(points: Vec<T>, box_lengths: Vec<T::Scalar>) -> Self {
+        debug_assert_eq!(box_lengths.len(), T::DIM, "one box length per dimension");
+        let mut tree = Self::build_by_ordered_float(points);
+        tree.periodic = Some(Periodic::new(box_lengths));
+        tree
+    }
+}
+
+impl<T: KdPoint> KdTree<T> {
+    /// Builds a tree using a custom per-axis comparator.
+    ///
+    /// `compare(a, b, k)` orders two points by their `k`-th coordinate. This is
+    /// the shared core behind [`build`](KdTree::build) and
+    /// [`build_by_ordered_float`](KdTree::build_by_ordered_float).
+    pub fn build_by(
+        mut points: Vec<T>,
+        compare: impl Fn(&T, &T, usize) -> Ordering + Copy,
+    ) -> Self {
+        kd_sort_by(&mut points, compare, 0);
+        Self {
+            items: points,
+            periodic: None,
+        }
+    }
+
+    /// Returns the number of stored points.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if the tree holds no points.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Iterates over the stored points in tree order.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.items.iter()
+    }
+
+    /// Consumes the tree and returns its points.
+    pub fn into_vec(self) -> Vec<T> {
+        self.items
+    }
+
+    /// Borrows the stored points as a slice, in tree order.
+    pub fn as_slice(&self) -> &[T] {
+        &self.items
+    }
+
+    /// Finds the single nearest neighbor of `query`, or `None` if empty.
+    ///
+    /// Uses squared [`Euclidean`] distance, unless the tree was built with
+    /// [`build_periodic`](KdTree::build_periodic), in which case minimum-image
+    /// distance is used. See [`nearest_by_metric`](KdTree::nearest_by_metric)
+    /// to pass an explicit metric.
+    pub fn nearest<P: KdPoint<Scalar = T::Scalar>>(
+        &self,
+        query: &P,
+    ) -> Option<ItemAndDistance<'_, T, T::Scalar>> {
+        match &self.periodic {
+            Some(periodic) => self.nearest_by_metric(query, periodic),
+            None => self.nearest_by_metric(query, &Euclidean),
+        }
+    }
+
+    /// Finds the single nearest neighbor of `query` under `metric`.
+    pub fn nearest_by_metric<P, M>(
+        &self,
+        query: &P,
+        metric: &M,
+    ) -> Option<ItemAndDistance<'_, T, T::Scalar>>
+    where
+        P: KdPoint<Scalar = T::Scalar>,
+        M: Metric<T::Scalar>,
+    {
+        let mut best: Option<ItemAndDistance<'_, T, T::Scalar>> = None;
+        nearest(&self.items, query, 0, metric, T::Scalar::one(), &mut best);
+        best
+    }
+
+    /// Approximate single nearest neighbor: a far subtree is pruned once its
+    /// lower-bound distance exceeds `best / (1 + epsilon)`, so the returned
+    /// neighbor is guaranteed to be within a `(1 + epsilon)` factor of the true
+    /// nearest. `epsilon = 0` reproduces [`nearest`](KdTree::nearest) exactly.
+    pub fn nearest_approx<P: KdPoint<Scalar = T::Scalar>>(
+        &self,
+        query: &P,
+        epsilon: T::Scalar,
+    ) -> Option<ItemAndDistance<'_, T, T::Scalar>> {
+        match &self.periodic {
+            Some(periodic) => self.nearest_approx_by_metric(query, epsilon, periodic),
+            None => self.nearest_approx_by_metric(query, epsilon, &Euclidean),
+        }
+    }
+
+    /// Approximate single nearest neighbor under `metric`; see
+    /// [`nearest_approx`](KdTree::nearest_approx).
+    pub fn nearest_approx_by_metric<P, M>(
+        &self,
+        query: &P,
+        epsilon: T::Scalar,
+        metric: &M,
+    ) -> Option<ItemAndDistance<'_, T, T::Scalar>>
+    where
+        P: KdPoint<Scalar = T::Scalar>,
+        M: Metric<T::Scalar>,
+    {
+        let mut best: Option<ItemAndDistance<'_, T, T::Scalar>> = None;
+        nearest(&self.items, query, 0, metric, shrink(metric, epsilon), &mut best);
+        best
+    }
+
+    /// Finds the `k` nearest neighbors of `query`, sorted by increasing distance.
+    ///
+    /// Fewer than `k` entries are returned only when the tree holds fewer than
+    /// `k` points.
+    pub fn nearests<P: KdPoint<Scalar = T::Scalar>>(
+        &self,
+        query: &P,
+        k: usize,
+    ) -> Vec<ItemAndDistance<'_, T, T::Scalar>> {
+        match &self.periodic {
+            Some(periodic) => self.nearests_by_metric(query, k, periodic),
+            None => self.nearests_by_metric(query, k, &Euclidean),
+        }
+    }
+
+    /// Finds the `k` nearest neighbors of `query` under `metric`.
+    pub fn nearests_by_metric<P, M>(
+        &self,
+        query: &P,
+        k: usize,
+        metric: &M,
+    ) -> Vec<ItemAndDistance<'_, T, T::Scalar>>
+    where
+        P: KdPoint<Scalar = T::Scalar>,
+        M: Metric<T::Scalar>,
+    {
+        let mut heap = KnnHeap::new(k);
+        nearests(&self.items, query, 0, metric, T::Scalar::one(), &mut heap);
+        heap.into_sorted_vec()
+    }
+
+    /// Approximate `k` nearest neighbors: a far subtree is pruned once its
+    /// lower-bound distance exceeds `worst / (1 + epsilon)`, trading exactness
+    /// for far fewer node visits on large trees. `epsilon = 0` reproduces
+    /// [`nearests`](KdTree::nearests) exactly.
+    pub fn nearests_approx<P: KdPoint<Scalar = T::Scalar>>(
+        &self,
+        query: &P,
+        k: usize,
+        epsilon: T::Scalar,
+    ) -> Vec<ItemAndDistance<'_, T, T::Scalar>> {
+        match &self.periodic {
+            Some(periodic) => self.nearests_approx_by_metric(query, k, epsilon, periodic),
+            None => self.nearests_approx_by_metric(query, k, epsilon, &Euclidean),
+        }
+    }
+
+    /// Approximate `k` nearest neighbors under `metric`; see
+    /// [`nearests_approx`](KdTree::nearests_approx).
+    pub fn nearests_approx_by_metric<P, M>(
+        &self,
+        query: &P,
+        k: usize,
+        epsilon: T::Scalar,
+        metric: &M,
+    ) -> Vec<ItemAndDistance<'_, T, T::Scalar>>
+    where
+        P: KdPoint<Scalar = T::Scalar>,
+        M: Metric<T::Scalar>,
+    {
+        let mut heap = KnnHeap::new(k);
+        nearests(&self.items, query, 0, metric, shrink(metric, epsilon), &mut heap);
+        heap.into_sorted_vec()
+    }
+
+    /// Advanced `k`-nearest-neighbor query honoring [`SearchParams`] and
+    /// reporting a touch count.
+    ///
+    /// Unlike [`nearests`](KdTree::nearests), a
+    /// [`max_radius`](SearchParams::max_radius) can cap results below `k`, and
+    /// [`allow_self_match`](SearchParams::allow_self_match) can drop a stored
+    /// point identical to the query. Uses the tree's default metric (periodic
+    /// when built with [`build_periodic`](KdTree::build_periodic), else
+    /// [`Euclidean`]).
+    pub fn nearests_advanced<P: KdPoint<Scalar = T::Scalar>>(
+        &self,
+        query: &P,
+        k: usize,
+        params: SearchParams<T::Scalar>,
+    ) -> SearchResult<'_, T, T::Scalar> {
+        match &self.periodic {
+            Some(periodic) => self.nearests_advanced_by_metric(query, k, params, periodic),
+            None => self.nearests_advanced_by_metric(query, k, params, &Euclidean),
+        }
+    }
+
+    /// Advanced `k`-nearest-neighbor query under `metric`; see
+    /// [`nearests_advanced`](KdTree::nearests_advanced).
+    pub fn nearests_advanced_by_metric<P, M>(
+        &self,
+        query: &P,
+        k: usize,
+        params: SearchParams<T::Scalar>,
+        metric: &M,
+    ) -> SearchResult<'_, T, T::Scalar>
+    where
+        P: KdPoint<Scalar = T::Scalar>,
+        M: Metric<T::Scalar>,
+    {
+        let max_domain = params.max_radius.map(|r| metric.radius_to_domain(r));
+        let mut heap = KnnHeap::new(k);
+        let mut touched = 0;
+        nearests_advanced(
+            &self.items,
+            query,
+            0,
+            metric,
+            max_domain,
+            params.allow_self_match,
+            &mut touched,
+            &mut heap,
+        );
+        let neighbors = if params.sort_results {
+            heap.into_sorted_vec()
+        } else {
+            heap.into_vec()
+        };
+        SearchResult { neighbors, touched }
+    }
+
+    /// Returns every stored point that lies in the closed axis-aligned box
+    /// spanned by `range[0]` (low corner) and `range[1]` (high corner).
+    pub fn within(&self, range: &[T; 2]) -> Vec<&T> {
+        let mut out = Vec::new();
+        within(&self.items, range, 0, &mut out);
+        out
+    }
+
+    /// Returns every stored point strictly within `radius` of `query`.
+    pub fn within_radius<P: KdPoint<Scalar = T::Scalar>>(
+        &self,
+        query: &P,
+        radius: T::Scalar,
+    ) -> Vec<&T> {
+        match &self.periodic {
+            Some(periodic) => self.within_radius_by_metric(query, radius, periodic),
+            None => self.within_radius_by_metric(query, radius, &Euclidean),
+        }
+    }
+
+    /// Returns every stored point strictly within `radius` of `query` under
+    /// `metric`.
+    pub fn within_radius_by_metric<P, M>(&self, query: &P, radius: T::Scalar, metric: &M) -> Vec<&T>
+    where
+        P: KdPoint<Scalar = T::Scalar>,
+        M: Metric<T::Scalar>,
+    {
+        let mut out = Vec::new();
+        within_radius(&self.items, query, metric.radius_to_domain(radius), 0, metric, &mut out);
+        out
+    }
+}
+
+/// Recursively permutes `items` into an implicit balanced kd-tree: the middle
+/// element is the median on the current axis, with smaller coordinates to its
+/// left and larger to its right, recursing on the next axis per level.
+fn kd_sort_by<T: KdPoint>(
+    items: &mut [T],
+    compare: impl Fn(&T, &T, usize) -> Ordering + Copy,
+    axis: usize,
+) {
+    if items.len() >= 2 {
+        let mid = items.len() / 2;
+        items.select_nth_unstable_by(mid, |a, b| compare(a, b, axis));
+        let next = (axis + 1) % T::DIM;
+        let (left, rest) = items.split_at_mut(mid);
+        kd_sort_by(left, compare, next);
+        kd_sort_by(&mut rest[1..], compare, next);
+    }
+}
+
+/// Pruning multiplier for an `epsilon`-approximate search, expressed in the
+/// metric's comparison domain: the far child is descended while
+/// `axis_bound < best * shrink`. A real-distance tolerance of `1 / (1 + epsilon)`
+/// maps into the domain through [`Metric::radius_to_domain`], so squared metrics
+/// get `1 / (1 + epsilon)^2` for free. `epsilon = 0` yields `1`, i.e. exact search.
+fn shrink<S, M>(metric: &M, epsilon: S) -> S
+where
+    S: Num + Copy + PartialOrd,
+    M: Metric<S>,
+{
+    metric.radius_to_domain(S::one() / (S::one() + epsilon))
+}
+
+/// Branch-and-bound single nearest-neighbor search over an implicit kd-tree
+/// slice, pruning the far subtree by the metric's per-axis lower bound on the
+/// splitting coordinate. `shrink` tightens the bound for approximate search
+/// (`1` for exact).
+fn nearest<'a, T, P, M>(
+    items: &'a [T],
+    query: &P,
+    axis: usize,
+    metric: &M,
+    shrink: T::Scalar,
+    best: &mut Option<ItemAndDistance<'a, T, T::Scalar>>,
+) where
+    T: KdPoint,
+    P: KdPoint<Scalar = T::Scalar>,
+    M: Metric<T::Scalar>,
+{
+    if items.is_empty() {
+        return;
+    }
+    let mid = items.len() / 2;
+    let node = &items[mid];
+    let d = metric.distance(node, query);
+    if best.as_ref().is_none_or(|b| d < b.squared_distance) {
+        *best = Some(ItemAndDistance {
+            item: node,
+            squared_distance: d,
+        });
+    }
+    let next = (axis + 1) % T::DIM;
+    let (near, far) = if query.at(axis) < node.at(axis) {
+        (&items[..mid], &items[mid + 1..])
+    } else {
+        (&items[mid + 1..], &items[..mid])
+    };
+    nearest(near, query, next, metric, shrink, best);
+    let bound = metric.axis_distance(axis, query.at(axis), node.at(axis));
+    if best
+        .as_ref()
+        .is_none_or(|b| bound < b.squared_distance * shrink)
+    {
+        nearest(far, query, next, metric, shrink, best);
+    }
+}
+
+/// Branch-and-bound k-nearest-neighbor search, bounded by a [`KnnHeap`].
+fn nearests<'a, T, P, M>(
+    items: &'a [T],
+    query: &P,
+    axis: usize,
+    metric: &M,
+    shrink: T::Scalar,
+    heap: &mut KnnHeap<'a, T, T::Scalar>,
+) where
+    T: KdPoint,
+    P: KdPoint<Scalar = T::Scalar>,
+    M: Metric<T::Scalar>,
+{
+    if items.is_empty() {
+        return;
+    }
+    let mid = items.len() / 2;
+    let node = &items[mid];
+    let d = metric.distance(node, query);
+    heap.offer(node, d);
+    let next = (axis + 1) % T::DIM;
+    let (near, far) = if query.at(axis) < node.at(axis) {
+        (&items[..mid], &items[mid + 1..])
+    } else {
+        (&items[mid + 1..], &items[..mid])
+    };
+    nearests(near, query, next, metric, shrink, heap);
+    let bound = metric.axis_distance(axis, query.at(axis), node.at(axis));
+    // `is_full()` is vacuously true for `k == 0` even though the heap (and thus
+    // `worst()`) is empty then, so also require a retained candidate before
+    // comparing against it.
+    if !heap.is_full() || (!heap.is_empty() && bound < heap.worst() * shrink) {
+        nearests(far, query, next, metric, shrink, heap);
+    }
+}
+
+/// Branch-and-bound k-nearest-neighbor search for
+/// [`KdTree::nearests_advanced`]. Counts visited nodes in `touched`, skips a
+/// self-match (distance zero) when `allow_self_match` is false, and keeps every
+/// candidate and subtree within `max_domain` when one is set.
+#[allow(clippy::too_many_arguments)]
+fn nearests_advanced<'a, T, P, M>(
+    items: &'a [T],
+    query: &P,
+    axis: usize,
+    metric: &M,
+    max_domain: Option<T::Scalar>,
+    allow_self_match: bool,
+    touched: &mut usize,
+    heap: &mut KnnHeap<'a, T, T::Scalar>,
+) where
+    T: KdPoint,
+    P: KdPoint<Scalar = T::Scalar>,
+    M: Metric<T::Scalar>,
+{
+    if items.is_empty() {
+        return;
+    }
+    *touched += 1;
+    let mid = items.len() / 2;
+    let node = &items[mid];
+    let d = metric.distance(node, query);
+    let is_self = !allow_self_match && d == T::Scalar::zero();
+    let in_radius = max_domain.is_none_or(|m| d <= m);
+    if !is_self && in_radius {
+        heap.offer(node, d);
+    }
+    let next = (axis + 1) % T::DIM;
+    let (near, far) = if query.at(axis) < node.at(axis) {
+        (&items[..mid], &items[mid + 1..])
+    } else {
+        (&items[mid + 1..], &items[..mid])
+    };
+    nearests_advanced(near, query, next, metric, max_domain, allow_self_match, touched, heap);
+    let bound = metric.axis_distance(axis, query.at(axis), node.at(axis));
+    let radius_ok = max_domain.is_none_or(|m| bound <= m);
+    // `is_full()` is vacuously true for `k == 0` even though the heap (and thus
+    // `worst()`) is empty then, so also require a retained candidate before
+    // comparing against it.
+    if radius_ok && (!heap.is_full() || (!heap.is_empty() && bound < heap.worst())) {
+        nearests_advanced(far, query, next, metric, max_domain, allow_self_match, touched, heap);
+    }
+}
+
+/// Recursive axis-aligned range scan for [`KdTree::within`].
+fn within<'a, T: KdPoint>(items: &'a [T], range: &[T; 2], axis: usize, out: &mut Vec<&'a T>) {
+    if items.is_empty() {
+        return;
+    }
+    let mid = items.len() / 2;
+    let node = &items[mid];
+    if (0..T::DIM).all(|k| !(node.at(k) < range[0].at(k)) && !(range[1].at(k) < node.at(k))) {
+        out.push(node);
+    }
+    let next = (axis + 1) % T::DIM;
+    if !(node.at(axis) < range[0].at(axis)) {
+        within(&items[..mid], range, next, out);
+    }
+    if !(range[1].at(axis) < node.at(axis)) {
+        within(&items[mid + 1..], range, next, out);
+    }
+}
+
+/// Recursive radius scan for [`KdTree::within_radius`]. `bound` is the radius
+/// already mapped into `metric`'s comparison domain.
+fn within_radius<'a, T, P, M>(
+    items: &'a [T],
+    query: &P,
+    bound: T::Scalar,
+    axis: usize,
+    metric: &M,
+    out: &mut Vec<&'a T>,
+) where
+    T: KdPoint,
+    P: KdPoint<Scalar = T::Scalar>,
+    M: Metric<T::Scalar>,
+{
+    if items.is_empty() {
+        return;
+    }
+    let mid = items.len() / 2;
+    let node = &items[mid];
+    if metric.distance(node, query) < bound {
+        out.push(node);
+    }
+    let next = (axis + 1) % T::DIM;
+    let (near, far) = if query.at(axis) < node.at(axis) {
+        (&items[..mid], &items[mid + 1..])
+    } else {
+        (&items[mid + 1..], &items[..mid])
+    };
+    within_radius(near, query, bound, next, metric, out);
+    if metric.axis_distance(axis, query.at(axis), node.at(axis)) < bound {
+        within_radius(far, query, bound, next, metric, out);
+    }
+}
+
+/// A bounded max-heap keeping the `k` smallest distances seen so far.
+///
+/// The worst (largest) of the retained candidates sits at the root so the
+/// search can prune against it in O(1).
+pub(crate) struct KnnHeap<'a, T, Scalar> {
+    k: usize,
+    items: Vec<ItemAndDistance<'a, T, Scalar>>,
+}
+
+impl<'a, T, Scalar: Copy + PartialOrd> KnnHeap<'a, T, Scalar> {
+    pub(crate) fn new(k: usize) -> Self {
+        Self {
+            k,
+            items: Vec::with_capacity(k),
+        }
+    }
+
+    pub(crate) fn is_full(&self) -> bool {
+        self.items.len() >= self.k
+    }
+
+    /// `true` when no candidate has been retained yet — always the case for
+    /// `k == 0`, since [`offer`](Self::offer) refuses to grow the heap then.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub(crate) fn worst(&self) -> Scalar {
+        self.items[0].squared_distance
+    }
+
+    /// Offers a candidate; retained only if the heap is not yet full or it beats
+    /// the current worst retained candidate.
+    pub(crate) fn offer(&mut self, item: &'a T, squared_distance: Scalar) {
+        if self.k == 0 {
+            return;
+        }
+        if self.items.len() < self.k {
+            self.items.push(ItemAndDistance {
+                item,
+                squared_distance,
+            });
+            if self.items.len() == self.k {
+                self.rebuild();
+            }
+        } else if squared_distance < self.items[0].squared_distance {
+            self.items[0] = ItemAndDistance {
+                item,
+                squared_distance,
+            };
+            self.sift_down(0);
+        }
+    }
+
+    fn rebuild(&mut self) {
+        for i in (0..self.items.len() / 2).rev() {
+            self.sift_down(i);
+        }
+    }
+
+    fn sift_down(&mut self, mut i: usize) {
+        let n = self.items.len();
+        loop {
+            let (l, r) = (2 * i + 1, 2 * i + 2);
+            let mut largest = i;
+            if l < n && self.items[l].squared_distance > self.items[largest].squared_distance {
+                largest = l;
+            }
+            if r < n && self.items[r].squared_distance > self.items[largest].squared_distance {
+                largest = r;
+            }
+            if largest == i {
+                break;
+            }
+            self.items.swap(i, largest);
+            i = largest;
+        }
+    }
+
+    pub(crate) fn into_vec(self) -> Vec<ItemAndDistance<'a, T, Scalar>> {
+        self.items
+    }
+
+    pub(crate) fn into_sorted_vec(mut self) -> Vec<ItemAndDistance<'a, T, Scalar>> {
+        self.items.sort_by(|a, b| {
+            a.squared_distance
+                .partial_cmp(&b.squared_distance)
+                .unwrap_or(Ordering::Equal)
+        });
+        self.items
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl<T: Num + Copy + PartialOrd + nalgebra::Scalar, const N: usize> KdPoint
+    for nalgebra::Point<T, N>
+{
+    type Scalar = T;
+    const DIM: usize = N;
+    fn at(&self, k: usize) -> T {
+        self.coords[k]
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl<T: Num + Copy + PartialOrd + nalgebra::Scalar, const N: usize> KdPoint
+    for nalgebra::SVector<T, N>
+{
+    type Scalar = T;
+    const DIM: usize = N;
+    fn at(&self, k: usize) -> T {
+        self[k]
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T: KdPoint + Send> KdTree<T>
+where
+    T::Scalar: Float,
+{
+    /// Parallel counterpart of [`build_by_ordered_float`](KdTree::build_by_ordered_float).
+    ///
+    /// Produces an identical tree to the sequential builder; only the median
+    /// selection at each level is spread across the rayon thread pool.
+    pub fn par_build_by_ordered_float(points: Vec<T>) -> Self {
+        Self::par_build_by(points, |a, b, k| {
+            ordered_float::OrderedFloat(a.at(k)).cmp(&ordered_float::OrderedFloat(b.at(k)))
+        })
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T: KdPoint + Send> KdTree<T> {
+    /// Parallel counterpart of [`build_by`](KdTree::build_by).
+    pub fn par_build_by(
+        mut points: Vec<T>,
+        compare: impl Fn(&T, &T, usize) -> std::cmp::Ordering + Copy + Sync,
+    ) -> Self {
+        par_kd_sort_by(&mut points, compare, 0);
+        Self {
+            items: points,
+            periodic: None,
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+fn par_kd_sort_by<T: KdPoint + Send>(
+    items: &mut [T],
+    compare: impl Fn(&T, &T, usize) -> std::cmp::Ordering + Copy + Sync,
+    axis: usize,
+) {
+    use rayon::join;
+    if items.len() >= 2 {
+        let mid = items.len() / 2;
+        items.select_nth_unstable_by(mid, |a, b| compare(a, b, axis));
+        let next = (axis + 1) % T::DIM;
+        let (left, rest) = items.split_at_mut(mid);
+        let right = &mut rest[1..];
+        join(
+            || par_kd_sort_by(left, compare, next),
+            || par_kd_sort_by(right, compare, next),
+        );
+    }
+}
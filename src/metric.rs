@@ -0,0 +1,161 @@
+//! Pluggable distance metrics.
+//!
+//! The tree's branch-and-bound traversal needs two things from a metric: the
+//! full distance between two points, and a per-axis lower-bound contribution
+//! for a single splitting coordinate. The latter must never overestimate the
+//! true distance, so that pruning the far subtree can never discard a genuine
+//! neighbor. Every metric here reports both in the same comparison domain (for
+//! [`Euclidean`] that domain is *squared* distance, which is why
+//! [`ItemAndDistance::squared_distance`](crate::ItemAndDistance) is squared).
+
+use num_traits::{Float, Num, Signed};
+
+use crate::KdPoint;
+
+/// A distance function usable by the kd-tree search.
+///
+/// Distances are compared but never shown to the user raw, so a metric is free
+/// to work in a monotonic surrogate domain (e.g. squared or `p`-th-power
+/// distance) as long as it is internally consistent across the three methods.
+pub trait Metric<S> {
+    /// Full distance between `a` and `b`, in this metric's comparison domain.
+    fn distance<A, B>(&self, a: &A, b: &B) -> S
+    where
+        A: KdPoint<Scalar = S>,
+        B: KdPoint<Scalar = S>;
+
+    /// Lower-bound contribution of axis `k`, whose coordinates are `a` and `b`,
+    /// in the same domain as [`distance`](Metric::distance).
+    ///
+    /// Summing (or maxing) these contributions over all axes must not exceed
+    /// the value [`distance`](Metric::distance) would return. The axis index is
+    /// supplied because metrics such as [`Periodic`](crate::Periodic) need
+    /// per-dimension parameters.
+    fn axis_distance(&self, k: usize, a: S, b: S) -> S;
+
+    /// Maps a user-facing radius into this metric's comparison domain.
+    fn radius_to_domain(&self, radius: S) -> S;
+}
+
+/// Squared Euclidean distance (L2). This is the tree's default metric.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Euclidean;
+
+impl<S: Num + Copy + PartialOrd> Metric<S> for Euclidean {
+    fn distance<A, B>(&self, a: &A, b: &B) -> S
+    where
+        A: KdPoint<Scalar = S>,
+        B: KdPoint<Scalar = S>,
+    {
+        let mut sum = S::zero();
+        for k in 0..A::DIM {
+            let d = a.at(k) - b.at(k);
+            sum = sum + d * d;
+        }
+        sum
+    }
+
+    fn axis_distance(&self, _k: usize, a: S, b: S) -> S {
+        let d = a - b;
+        d * d
+    }
+
+    fn radius_to_domain(&self, radius: S) -> S {
+        radius * radius
+    }
+}
+
+/// Manhattan distance (L1): the sum of per-axis absolute differences.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Manhattan;
+
+impl<S: Signed + Copy + PartialOrd> Metric<S> for Manhattan {
+    fn distance<A, B>(&self, a: &A, b: &B) -> S
+    where
+        A: KdPoint<Scalar = S>,
+        B: KdPoint<Scalar = S>,
+    {
+        let mut sum = S::zero();
+        for k in 0..A::DIM {
+            sum = sum + (a.at(k) - b.at(k)).abs();
+        }
+        sum
+    }
+
+    fn axis_distance(&self, _k: usize, a: S, b: S) -> S {
+        (a - b).abs()
+    }
+
+    fn radius_to_domain(&self, radius: S) -> S {
+        radius
+    }
+}
+
+/// Chebyshev distance (L∞): the largest per-axis absolute difference.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Chebyshev;
+
+impl<S: Signed + Copy + PartialOrd> Metric<S> for Chebyshev {
+    fn distance<A, B>(&self, a: &A, b: &B) -> S
+    where
+        A: KdPoint<Scalar = S>,
+        B: KdPoint<Scalar = S>,
+    {
+        let mut max = S::zero();
+        for k in 0..A::DIM {
+            let d = (a.at(k) - b.at(k)).abs();
+            if d > max {
+                max = d;
+            }
+        }
+        max
+    }
+
+    fn axis_distance(&self, _k: usize, a: S, b: S) -> S {
+        (a - b).abs()
+    }
+
+    fn radius_to_domain(&self, radius: S) -> S {
+        radius
+    }
+}
+
+/// General Minkowski-`p` distance, reported as the sum of `|d|^p` (the outer
+/// `p`-th root is omitted because it is monotonic and would only cost a call).
+///
+/// `p = 1` coincides with [`Manhattan`], and `p = 2` reports the same squared
+/// value as [`Euclidean`].
+#[derive(Clone, Copy, Debug)]
+pub struct Minkowski {
+    /// The order `p` of the norm.
+    pub p: i32,
+}
+
+impl Minkowski {
+    /// Creates a Minkowski metric of order `p`.
+    pub fn new(p: i32) -> Self {
+        Self { p }
+    }
+}
+
+impl<S: Float> Metric<S> for Minkowski {
+    fn distance<A, B>(&self, a: &A, b: &B) -> S
+    where
+        A: KdPoint<Scalar = S>,
+        B: KdPoint<Scalar = S>,
+    {
+        let mut sum = S::zero();
+        for k in 0..A::DIM {
+            sum = sum + (a.at(k) - b.at(k)).abs().powi(self.p);
+        }
+        sum
+    }
+
+    fn axis_distance(&self, _k: usize, a: S, b: S) -> S {
+        (a - b).abs().powi(self.p)
+    }
+
+    fn radius_to_domain(&self, radius: S) -> S {
+        radius.powi(self.p)
+    }
+}
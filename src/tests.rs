@@ -91,6 +91,391 @@ fn test_within_radius() {
     }
 }
 
+#[test]
+fn test_nearest_periodic() {
+    let mut gen3d = random3d_generator();
+    let kdtree = KdTree::build_periodic(vec(10000, |_| gen3d()), vec![1.0, 1.0, 1.0]);
+    for _ in 0..100 {
+        let query = gen3d();
+        let found = kdtree.nearest(&query).unwrap().item;
+        let expected = kdtree
+            .iter()
+            .min_by_key(|p| {
+                ordered_float::OrderedFloat(periodic_squared_distance(p, &query, &[1.0; 3]))
+            })
+            .unwrap();
+        assert_eq!(
+            periodic_squared_distance(found, &query, &[1.0; 3]),
+            periodic_squared_distance(expected, &query, &[1.0; 3])
+        );
+    }
+}
+
+#[test]
+fn test_within_radius_periodic() {
+    let mut gen3d = random3d_generator();
+    let kdtree = KdTree::build_periodic(vec(10000, |_| gen3d()), vec![1.0, 1.0, 1.0]);
+    const RADIUS: f64 = 0.1;
+    for _ in 0..100 {
+        let query = gen3d();
+        let found = kdtree.within_radius(&query, RADIUS);
+        let count = kdtree
+            .iter()
+            .filter(|p| periodic_squared_distance(p, &query, &[1.0; 3]) < RADIUS * RADIUS)
+            .count();
+        assert_eq!(found.len(), count);
+    }
+}
+
+#[test]
+fn test_nearest_by_metric() {
+    let mut gen3d = random3d_generator();
+    let kdtree = KdTree::build_by_ordered_float(vec(10000, |_| gen3d()));
+    for _ in 0..100 {
+        let query = gen3d();
+
+        let found = kdtree.nearest_by_metric(&query, &Manhattan).unwrap().item;
+        let expected = kdtree
+            .iter()
+            .min_by_key(|p| ordered_float::OrderedFloat(manhattan_distance(p, &query)))
+            .unwrap();
+        assert_eq!(
+            manhattan_distance(found, &query),
+            manhattan_distance(expected, &query)
+        );
+
+        let found = kdtree.nearest_by_metric(&query, &Chebyshev).unwrap().item;
+        let expected = kdtree
+            .iter()
+            .min_by_key(|p| ordered_float::OrderedFloat(chebyshev_distance(p, &query)))
+            .unwrap();
+        assert_eq!(
+            chebyshev_distance(found, &query),
+            chebyshev_distance(expected, &query)
+        );
+
+        const P: i32 = 3;
+        let found = kdtree.nearest_by_metric(&query, &Minkowski::new(P)).unwrap().item;
+        let expected = kdtree
+            .iter()
+            .min_by_key(|p| ordered_float::OrderedFloat(minkowski_distance(p, &query, P)))
+            .unwrap();
+        assert_eq!(
+            minkowski_distance(found, &query, P),
+            minkowski_distance(expected, &query, P)
+        );
+    }
+}
+
+#[test]
+fn test_within_radius_by_metric() {
+    let mut gen3d = random3d_generator();
+    let kdtree = KdTree::build_by_ordered_float(vec(10000, |_| gen3d()));
+    const RADIUS: f64 = 0.2;
+    for _ in 0..100 {
+        let query = gen3d();
+
+        let found = kdtree.within_radius_by_metric(&query, RADIUS, &Manhattan);
+        let count = kdtree
+            .iter()
+            .filter(|p| manhattan_distance(p, &query) < RADIUS)
+            .count();
+        assert_eq!(found.len(), count);
+
+        let found = kdtree.within_radius_by_metric(&query, RADIUS, &Chebyshev);
+        let count = kdtree
+            .iter()
+            .filter(|p| chebyshev_distance(p, &query) < RADIUS)
+            .count();
+        assert_eq!(found.len(), count);
+
+        const P: i32 = 3;
+        let found = kdtree.within_radius_by_metric(&query, RADIUS, &Minkowski::new(P));
+        let count = kdtree
+            .iter()
+            .filter(|p| minkowski_distance(p, &query, P) < RADIUS.powi(P))
+            .count();
+        assert_eq!(found.len(), count);
+    }
+}
+
+fn manhattan_distance(p1: &[f64; 3], p2: &[f64; 3]) -> f64 {
+    (0..3).map(|k| (p1[k] - p2[k]).abs()).sum()
+}
+
+fn chebyshev_distance(p1: &[f64; 3], p2: &[f64; 3]) -> f64 {
+    (0..3).map(|k| (p1[k] - p2[k]).abs()).fold(0.0, f64::max)
+}
+
+/// Reported in the same domain as [`Minkowski::distance`](crate::metric::Metric::distance),
+/// i.e. `sum(|d|^p)` with the outer `p`-th root omitted.
+fn minkowski_distance(p1: &[f64; 3], p2: &[f64; 3], p: i32) -> f64 {
+    (0..3).map(|k| (p1[k] - p2[k]).abs().powi(p)).sum()
+}
+
+#[test]
+fn test_nearest_approx_zero_epsilon() {
+    let mut gen3d = random3d_generator();
+    let kdtree = KdTree::build_by_ordered_float(vec(10000, |_| gen3d()));
+    for _ in 0..100 {
+        let query = gen3d();
+        let exact = kdtree.nearest(&query).unwrap().item;
+        let approx = kdtree.nearest_approx(&query, 0.0).unwrap().item;
+        assert_eq!(exact, approx);
+    }
+}
+
+#[test]
+fn test_nearest_approx_bound() {
+    let mut gen3d = random3d_generator();
+    let kdtree = KdTree::build_by_ordered_float(vec(10000, |_| gen3d()));
+    const EPSILON: f64 = 0.5;
+    for _ in 0..100 {
+        let query = gen3d();
+        let exact = kdtree.nearest(&query).unwrap().squared_distance;
+        let approx = kdtree.nearest_approx(&query, EPSILON).unwrap().squared_distance;
+        assert!(approx.sqrt() <= (1.0 + EPSILON) * exact.sqrt() + f64::EPSILON);
+    }
+}
+
+#[test]
+fn test_nearests_advanced_max_radius() {
+    let mut gen3d = random3d_generator();
+    let points = vec(10000, |_| gen3d());
+    let kdtree = KdTree::build_by_ordered_float(points.clone());
+    const RADIUS: f64 = 0.1;
+    let params = SearchParams {
+        max_radius: Some(RADIUS),
+        ..Default::default()
+    };
+    for _ in 0..100 {
+        let query = gen3d();
+        let result = kdtree.nearests_advanced(&query, 10000, params);
+        assert!(result.touched > 0);
+        let count = points
+            .iter()
+            .filter(|p| squared_distance(p, &query) <= RADIUS * RADIUS)
+            .count();
+        assert_eq!(result.neighbors.len(), count);
+        for entry in &result.neighbors {
+            assert!(entry.squared_distance <= RADIUS * RADIUS);
+        }
+    }
+}
+
+#[test]
+fn test_nearests_advanced_self_match() {
+    let points = vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+    let kdtree = KdTree::build_by_ordered_float(points);
+    let query = [0.0, 0.0, 0.0];
+    let params = SearchParams {
+        allow_self_match: false,
+        ..Default::default()
+    };
+    let result = kdtree.nearests_advanced(&query, 3, params);
+    assert!(result.neighbors.iter().all(|e| e.squared_distance > 0.0));
+    assert_eq!(result.neighbors.len(), 2);
+}
+
+#[test]
+fn test_nearests_advanced_zero_k_returns_empty() {
+    let mut gen3d = random3d_generator();
+    let kdtree = KdTree::build_by_ordered_float(vec(2000, |_| gen3d()));
+    let query = gen3d();
+    let result = kdtree.nearests_advanced(&query, 0, SearchParams::default());
+    assert!(result.neighbors.is_empty());
+}
+
+#[test]
+fn test_nearests_approx_zero_epsilon() {
+    let mut gen3d = random3d_generator();
+    let kdtree = KdTree::build_by_ordered_float(vec(10000, |_| gen3d()));
+    const NUM: usize = 5;
+    for _ in 0..100 {
+        let query = gen3d();
+        let exact = kdtree.nearests(&query, NUM);
+        let approx = kdtree.nearests_approx(&query, NUM, 0.0);
+        assert_eq!(exact.len(), approx.len());
+        for (e, a) in exact.iter().zip(&approx) {
+            assert_eq!(e.item, a.item);
+        }
+    }
+}
+
+#[test]
+fn test_nearests_approx_bound() {
+    let mut gen3d = random3d_generator();
+    let kdtree = KdTree::build_by_ordered_float(vec(10000, |_| gen3d()));
+    const NUM: usize = 5;
+    const EPSILON: f64 = 0.5;
+    for _ in 0..100 {
+        let query = gen3d();
+        let exact = kdtree.nearests(&query, NUM);
+        let approx = kdtree.nearests_approx(&query, NUM, EPSILON);
+        assert_eq!(approx.len(), exact.len());
+        for (e, a) in exact.iter().zip(&approx) {
+            assert!(a.squared_distance.sqrt() <= (1.0 + EPSILON) * e.squared_distance.sqrt() + f64::EPSILON);
+        }
+    }
+}
+
+#[test]
+fn test_dynamic_insert() {
+    let mut gen3d = random3d_generator();
+    let points = vec(10000, |_| gen3d());
+    let mut dynamic = DynamicKdTree::new_by_ordered_float();
+    for p in &points {
+        dynamic.insert(*p);
+    }
+    assert_eq!(dynamic.len(), points.len());
+    for _ in 0..100 {
+        let query = gen3d();
+        let found = dynamic.nearest(&query).unwrap().item;
+        let expected = points
+            .iter()
+            .min_by_key(|p| ordered_float::OrderedFloat(squared_distance(p, &query)))
+            .unwrap();
+        assert_eq!(squared_distance(found, &query), squared_distance(expected, &query));
+    }
+}
+
+#[test]
+fn test_dynamic_remove() {
+    let mut gen3d = random3d_generator();
+    let points = vec(2000, |_| gen3d());
+    let mut dynamic = DynamicKdTree::build_by_ordered_float(points.clone());
+    let (removed, kept) = points.split_at(points.len() / 2);
+    for p in removed {
+        assert!(dynamic.remove(p));
+    }
+    assert_eq!(dynamic.len(), kept.len());
+    for _ in 0..100 {
+        let query = gen3d();
+        let found = dynamic.nearest(&query).unwrap().item;
+        let expected = kept
+            .iter()
+            .min_by_key(|p| ordered_float::OrderedFloat(squared_distance(p, &query)))
+            .unwrap();
+        assert_eq!(squared_distance(found, &query), squared_distance(expected, &query));
+    }
+}
+
+#[test]
+fn test_dynamic_remove_duplicate_point_stays_removed_after_compaction() {
+    let p = [1.0, 2.0, 3.0];
+    let mut dynamic = DynamicKdTree::build_by_ordered_float(vec![p, p]);
+    assert!(dynamic.remove(&p));
+    // Both copies are tombstoned, and tombstones (2) * 2 >= stored_len() (2)
+    // triggers compaction immediately,
+    // so this also exercises compact() itself, not just the pre-compaction filter.
+    assert_eq!(dynamic.len(), 0);
+    assert!(dynamic.nearest(&p).is_none());
+    assert!(dynamic.nearests(&p, 2).is_empty());
+}
+
+#[test]
+fn test_dynamic_nearests_zero_k_returns_empty() {
+    let mut gen3d = random3d_generator();
+    let dynamic = DynamicKdTree::build_by_ordered_float(vec(2000, |_| gen3d()));
+    let query = gen3d();
+    assert!(dynamic.nearests(&query, 0).is_empty());
+}
+
+#[test]
+fn test_vp_tree_nearest() {
+    let mut gen3d = random3d_generator();
+    let points = vec(2000, |_| gen3d());
+    let vptree = VpTree::build(points.clone(), |a: &[f64; 3], b: &[f64; 3]| {
+        squared_distance(a, b).sqrt()
+    });
+    for _ in 0..100 {
+        let query = gen3d();
+        let found = vptree.nearest(&query).unwrap().item;
+        let expected = points
+            .iter()
+            .min_by_key(|p| ordered_float::OrderedFloat(squared_distance(p, &query)))
+            .unwrap();
+        assert_eq!(squared_distance(found, &query), squared_distance(expected, &query));
+    }
+}
+
+#[test]
+fn test_vp_tree_within_radius() {
+    let mut gen3d = random3d_generator();
+    let points = vec(2000, |_| gen3d());
+    let vptree = VpTree::build(points.clone(), |a: &[f64; 3], b: &[f64; 3]| {
+        squared_distance(a, b).sqrt()
+    });
+    const RADIUS: f64 = 0.1;
+    for _ in 0..100 {
+        let query = gen3d();
+        let found = vptree.within_radius(&query, RADIUS);
+        let count = points
+            .iter()
+            .filter(|p| squared_distance(p, &query).sqrt() < RADIUS)
+            .count();
+        assert_eq!(found.len(), count);
+    }
+}
+
+#[test]
+fn test_vp_tree_nearests_zero_k_returns_empty() {
+    let mut gen3d = random3d_generator();
+    let points = vec(2000, |_| gen3d());
+    let vptree = VpTree::build(points, |a: &[f64; 3], b: &[f64; 3]| squared_distance(a, b).sqrt());
+    let query = gen3d();
+    assert!(vptree.nearests(&query, 0).is_empty());
+}
+
+#[test]
+fn test_periodic_edge_queries() {
+    // A small, deliberately edge-heavy cloud: the nearest neighbor of a query
+    // near one face is often a point near the opposite face, reachable only by
+    // wrapping. Every query must match a brute-force min-image scan.
+    let lengths = [1.0, 1.0, 1.0];
+    let points = vec![
+        [0.02, 0.5, 0.5],
+        [0.98, 0.5, 0.5],
+        [0.5, 0.03, 0.5],
+        [0.5, 0.97, 0.5],
+        [0.5, 0.5, 0.01],
+        [0.5, 0.5, 0.99],
+        [0.01, 0.01, 0.01],
+        [0.99, 0.99, 0.99],
+        [0.5, 0.5, 0.5],
+    ];
+    let kdtree = KdTree::build_periodic(points.clone(), lengths.to_vec());
+    let queries = [
+        [0.0, 0.5, 0.5],
+        [0.99, 0.5, 0.5],
+        [0.5, 0.0, 0.5],
+        [0.5, 0.5, 0.0],
+        [0.995, 0.005, 0.995],
+        [0.001, 0.999, 0.001],
+    ];
+    for query in &queries {
+        let found = kdtree.nearest(query).unwrap().item;
+        let expected = points
+            .iter()
+            .min_by_key(|p| {
+                ordered_float::OrderedFloat(periodic_squared_distance(p, query, &lengths))
+            })
+            .unwrap();
+        assert_eq!(
+            periodic_squared_distance(found, query, &lengths),
+            periodic_squared_distance(expected, query, &lengths),
+        );
+
+        const RADIUS: f64 = 0.15;
+        let within = kdtree.within_radius(query, RADIUS);
+        let count = points
+            .iter()
+            .filter(|p| periodic_squared_distance(p, query, &lengths) < RADIUS * RADIUS)
+            .count();
+        assert_eq!(within.len(), count);
+    }
+}
+
 fn squared_distance<T: num_traits::Num + Copy>(p1: &[T; 3], p2: &[T; 3]) -> T {
     let dx = p1[0] - p2[0];
     let dy = p1[1] - p2[1];
@@ -98,6 +483,17 @@ fn squared_distance<T: num_traits::Num + Copy>(p1: &[T; 3], p2: &[T; 3]) -> T {
     dx * dx + dy * dy + dz * dz
 }
 
+/// Brute-force minimum-image squared distance used to check periodic queries.
+fn periodic_squared_distance(p1: &[f64; 3], p2: &[f64; 3], lengths: &[f64; 3]) -> f64 {
+    (0..3)
+        .map(|k| {
+            let dx = (p1[k] - p2[k]).abs();
+            let dx = if dx > lengths[k] / 2.0 { lengths[k] - dx } else { dx };
+            dx * dx
+        })
+        .sum()
+}
+
 fn random3d_generator() -> impl FnMut() -> [f64; 3] {
     use rand::Rng;
     let mut rng = rand::rng();
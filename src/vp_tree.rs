@@ -0,0 +1,196 @@
+//! Vantage-point tree for purely metric spaces.
+//!
+//! A [`KdTree`](crate::KdTree) needs axis-aligned coordinates. When the data is
+//! only reachable through a distance function — colors in a perceptual space,
+//! arbitrary embeddings — a vantage-point tree indexes it instead. Each node
+//! picks a vantage point, measures the distance from it to the remaining items,
+//! and splits at the median radius into an `inside` subtree (no farther than
+//! the threshold) and an `outside` subtree (at least the threshold away). Search keeps a best-distance bound and
+//! prunes a subtree whenever `|dist(query, vantage) − threshold|` exceeds that
+//! bound (the triangle inequality).
+//!
+//! The public surface mirrors [`KdTree`](crate::KdTree)'s
+//! [`nearest`](VpTree::nearest) / [`nearests`](VpTree::nearests) /
+//! [`within_radius`](VpTree::within_radius), so a caller can swap backends by
+//! whether their space is coordinate-based or purely metric. Because a metric
+//! space has no notion of a squared coordinate gap,
+//! [`ItemAndDistance::squared_distance`](crate::ItemAndDistance) here holds the
+//! raw distance returned by the supplied function.
+
+use std::cmp::Ordering;
+
+use num_traits::Float;
+
+use crate::{ItemAndDistance, KnnHeap};
+
+/// One node of a [`VpTree`]: a vantage point, the median split radius, and the
+/// inside/outside subtrees.
+#[derive(Clone, Debug)]
+struct VpNode<S> {
+    /// Index into [`VpTree::items`] of this node's vantage point.
+    center: usize,
+    /// Median distance from the vantage point to its descendants.
+    threshold: S,
+    /// Descendants no farther than `threshold`; points at exactly `threshold`
+    /// may sit on either side (the median point itself goes `outside`).
+    inside: Option<Box<VpNode<S>>>,
+    /// Descendants at least `threshold` away.
+    outside: Option<Box<VpNode<S>>>,
+}
+
+/// A metric-space search tree built from items and a user distance function.
+///
+/// Build one with [`VpTree::build`]; the distance closure is retained so the
+/// same metric is used for every query.
+#[derive(Clone, Debug)]
+pub struct VpTree<T, S, F> {
+    items: Vec<T>,
+    root: Option<Box<VpNode<S>>>,
+    distance: F,
+}
+
+impl<T, S, F> VpTree<T, S, F>
+where
+    S: Float,
+    F: Fn(&T, &T) -> S,
+{
+    /// Builds a tree over `items`, measuring distances with `distance`.
+    pub fn build(items: Vec<T>, distance: F) -> Self {
+        let mut indices: Vec<usize> = (0..items.len()).collect();
+        let root = build_node(&items, &distance, &mut indices);
+        Self {
+            items,
+            root,
+            distance,
+        }
+    }
+
+    /// Returns the number of stored items.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if the tree holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Iterates over the stored items.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.items.iter()
+    }
+
+    /// Finds the single nearest neighbor of `query`, or `None` if empty.
+    pub fn nearest(&self, query: &T) -> Option<ItemAndDistance<'_, T, S>> {
+        self.nearests(query, 1).into_iter().next()
+    }
+
+    /// Finds the `k` nearest neighbors of `query`, sorted by increasing distance.
+    pub fn nearests(&self, query: &T, k: usize) -> Vec<ItemAndDistance<'_, T, S>> {
+        let mut heap = KnnHeap::new(k);
+        if let Some(root) = &self.root {
+            self.search(root, query, &mut heap);
+        }
+        heap.into_sorted_vec()
+    }
+
+    /// Returns every stored item strictly within `radius` of `query`.
+    pub fn within_radius(&self, query: &T, radius: S) -> Vec<&T> {
+        let mut out = Vec::new();
+        if let Some(root) = &self.root {
+            self.search_radius(root, query, radius, &mut out);
+        }
+        out
+    }
+
+    /// Current pruning bound: the worst retained distance, or infinity while the
+    /// heap is not yet full (including `k == 0`, where it never holds any
+    /// candidate and `is_full()` is vacuously true).
+    fn tau(&self, heap: &KnnHeap<'_, T, S>) -> S {
+        if heap.is_full() && !heap.is_empty() {
+            heap.worst()
+        } else {
+            S::infinity()
+        }
+    }
+
+    fn search<'a>(&'a self, node: &VpNode<S>, query: &T, heap: &mut KnnHeap<'a, T, S>) {
+        let d = (self.distance)(&self.items[node.center], query);
+        heap.offer(&self.items[node.center], d);
+        if d < node.threshold {
+            if let Some(inside) = &node.inside {
+                self.search(inside, query, heap);
+            }
+            if node.threshold - d <= self.tau(heap) {
+                if let Some(outside) = &node.outside {
+                    self.search(outside, query, heap);
+                }
+            }
+        } else {
+            if let Some(outside) = &node.outside {
+                self.search(outside, query, heap);
+            }
+            if d - node.threshold <= self.tau(heap) {
+                if let Some(inside) = &node.inside {
+                    self.search(inside, query, heap);
+                }
+            }
+        }
+    }
+
+    fn search_radius<'a>(
+        &'a self,
+        node: &VpNode<S>,
+        query: &T,
+        radius: S,
+        out: &mut Vec<&'a T>,
+    ) {
+        let d = (self.distance)(&self.items[node.center], query);
+        if d < radius {
+            out.push(&self.items[node.center]);
+        }
+        if d - radius < node.threshold {
+            if let Some(inside) = &node.inside {
+                self.search_radius(inside, query, radius, out);
+            }
+        }
+        if d + radius >= node.threshold {
+            if let Some(outside) = &node.outside {
+                self.search_radius(outside, query, radius, out);
+            }
+        }
+    }
+}
+
+/// Recursively builds a node from `indices` (the vantage point is `indices[0]`,
+/// the rest are partitioned at the median distance from it).
+fn build_node<T, S, F>(items: &[T], distance: &F, indices: &mut [usize]) -> Option<Box<VpNode<S>>>
+where
+    S: Float,
+    F: Fn(&T, &T) -> S,
+{
+    let (center, rest) = indices.split_first_mut()?;
+    let center = *center;
+    if rest.is_empty() {
+        return Some(Box::new(VpNode {
+            center,
+            threshold: S::zero(),
+            inside: None,
+            outside: None,
+        }));
+    }
+    let mid = rest.len() / 2;
+    rest.select_nth_unstable_by(mid, |&a, &b| {
+        let da = distance(&items[center], &items[a]);
+        let db = distance(&items[center], &items[b]);
+        da.partial_cmp(&db).unwrap_or(Ordering::Equal)
+    });
+    let threshold = distance(&items[center], &items[rest[mid]]);
+    let (inside, outside) = rest.split_at_mut(mid);
+    Some(Box::new(VpNode {
+        center,
+        threshold,
+        inside: build_node(items, distance, inside),
+        outside: build_node(items, distance, outside),
+    }))
+}